@@ -1,65 +1,115 @@
 use core::hash::Hash;
-use std::cmp::Ord;
+use std::cmp::{Ord, Reverse};
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::ops::Add;
 
-pub trait Graph<T: Ord + Hash> {
-    fn get_successors(&self, from: &T) -> Vec<(&T, i32)>;
+/// A numeric edge weight.
+///
+/// The routing core is generic over the weight type so the same code works for
+/// `i32`/`u32` hop counts, wide `u64` IGP metrics that would overflow `i32` on
+/// long paths, or a newtype wrapping `f64` bits for fractional link weights.
+pub trait Weight: Copy + Ord + Add<Output = Self> + Default {
+    /// The additive identity, used as the cost of the source node.
+    fn zero() -> Self;
 }
 
-impl Graph<usize> for Vec<Vec<(usize, i32)>> {
-    fn get_successors(&self, from: &usize) -> Vec<(&usize, i32)> {
+impl<W: Copy + Ord + Add<Output = W> + Default> Weight for W {
+    fn zero() -> Self {
+        W::default()
+    }
+}
+
+pub trait Graph<T: Ord + Hash, W: Weight> {
+    fn get_successors(&self, from: &T) -> Vec<(&T, W)>;
+}
+
+impl<W: Weight> Graph<usize, W> for Vec<Vec<(usize, W)>> {
+    fn get_successors(&self, from: &usize) -> Vec<(&usize, W)> {
         self.get(*from)
             .unwrap()
             .iter()
-            .map(|(node, cost)| (node, *cost as i32))
+            .map(|(node, cost)| (node, *cost))
             .collect()
     }
 }
 
-pub fn dijkstra<'a, T: Ord + Hash>(
-    graph: &'a dyn Graph<T>,
-    start: &'a T,
-) -> Option<HashMap<&'a T, Vec<&'a T>>> {
-    let mut heap: BinaryHeap<(i32, (&T, &T))> = BinaryHeap::new();
-    let mut visited: HashSet<&T> = HashSet::new();
-    let mut cost_to_reach: HashMap<&T, i32> = HashMap::new();
-    let mut predecessors: HashMap<&T, Vec<&T>> = HashMap::new();
+/// Lazy, cost-ordered traversal of a [`Graph`] from a single source.
+///
+/// Each call to [`next`](Iterator::next) yields `(node, cost_to_reach,
+/// predecessor)` in nondecreasing cost order, so callers that only care about a
+/// single destination, the `k` nearest routers, or reachability within a cost
+/// budget can stop iterating early instead of computing the whole shortest-path
+/// tree. Draining the iterator to exhaustion visits every reachable node.
+///
+/// A node is yielded once when it is first settled (with its optimal cost) and
+/// again for each equal-cost predecessor that reaches it, which is what lets
+/// callers recover the ECMP predecessor sets.
+pub struct DijkstraIter<'a, T: Ord + Hash, W: Weight> {
+    graph: &'a dyn Graph<T, W>,
+    heap: BinaryHeap<(Reverse<W>, &'a T, &'a T)>,
+    seen: HashSet<&'a T>,
+    cost_to_reach: HashMap<&'a T, W>,
+}
+
+impl<'a, T: Ord + Hash, W: Weight> DijkstraIter<'a, T, W> {
+    /// Create a traversal rooted at `start`.
+    pub fn new(graph: &'a dyn Graph<T, W>, start: &'a T) -> Self {
+        let mut heap = BinaryHeap::new();
+        heap.push((Reverse(W::zero()), start, start));
+        DijkstraIter {
+            graph,
+            heap,
+            seen: HashSet::new(),
+            cost_to_reach: HashMap::new(),
+        }
+    }
+}
 
-    heap.push((0, (start, start)));
-    while !heap.is_empty() {
-        let (cost, (current, from)) = match heap.pop() {
-            Some(infos) => infos,
-            None => return None,
-        };
-
-        if visited.contains(current) {
-            // Maybe ECMP?
-            match cost_to_reach.get(current) {
-                None => continue,
-                Some(optimal_cost) => {
-                    if *optimal_cost == cost {
-                        // This is ECMP!
-                        predecessors.entry(current).or_insert_with(Vec::new).push(from);
-                    }
+impl<'a, T: Ord + Hash, W: Weight> Iterator for DijkstraIter<'a, T, W> {
+    type Item = (&'a T, W, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((Reverse(cost), current, from)) = self.heap.pop() {
+            if self.seen.contains(current) {
+                // Already settled: only keep it if this is an equal-cost
+                // alternative, i.e. an ECMP predecessor. Do not re-expand.
+                if self.cost_to_reach.get(current) == Some(&cost) {
+                    return Some((current, cost, from));
                 }
+                continue;
             }
-            // Do not need to expand the node, we already did it
-            continue;
-        }
 
-        visited.insert(current);
-        predecessors.entry(current).or_insert_with(Vec::new).push(from);
-        cost_to_reach.insert(current, cost);
+            self.seen.insert(current);
+            self.cost_to_reach.insert(current, cost);
 
-        // Add all neighbours
-        for (neigh, local_cost) in graph
-            .get_successors(current)
-            .iter()
-            .filter(|(neigh, _)| !visited.contains(neigh))
-        {
-            heap.push((cost - local_cost, (neigh, current)));
+            // Add all unvisited neighbours at their accumulated cost.
+            for (neigh, local_cost) in self
+                .graph
+                .get_successors(current)
+                .into_iter()
+                .filter(|(neigh, _)| !self.seen.contains(neigh))
+            {
+                self.heap.push((Reverse(cost + local_cost), neigh, current));
+            }
+
+            return Some((current, cost, from));
         }
+        None
     }
+}
+
+pub fn dijkstra<'a, T: Ord + Hash, W: Weight>(
+    graph: &'a dyn Graph<T, W>,
+    start: &'a T,
+) -> Option<HashMap<&'a T, Vec<&'a T>>> {
+    let mut predecessors: HashMap<&T, Vec<&T>> = HashMap::new();
+
+    // Drain the lazy traversal and accumulate every (equal-cost) predecessor,
+    // yielding the full ECMP shortest-path tree.
+    for (node, _cost, from) in DijkstraIter::new(graph, start) {
+        predecessors.entry(node).or_insert_with(Vec::new).push(from);
+    }
+
     Some(predecessors)
 }
 
@@ -144,6 +194,54 @@ mod tests {
         assert!(nh_unw.get(&3).unwrap().contains(&&1));
         assert!(nh_unw.get(&3).unwrap().contains(&&2));
     }
+    #[test]
+    fn test_dijkstra_iter_cost_order() {
+        let mut v: Vec<Vec<(usize, i32)>> = Vec::with_capacity(5);
+        v.push(vec![(1, 1), (2, 1)]);
+        v.push(vec![(0, 1), (3, 1)]);
+        v.push(vec![(0, 1), (3, 2)]);
+        v.push(vec![(1, 1), (2, 2), (4, 1)]);
+        v.push(vec![(3, 1)]);
+
+        let start: usize = 1;
+
+        // Every yield comes out in nondecreasing cost order, and the first
+        // yield of each node carries its optimal cost.
+        let mut last_cost = -1;
+        let mut first_cost: HashMap<usize, i32> = HashMap::new();
+        for (node, cost, _from) in DijkstraIter::new(&v, &start) {
+            assert!(cost >= last_cost);
+            last_cost = cost;
+            first_cost.entry(*node).or_insert(cost);
+        }
+
+        assert_eq!(first_cost[&1], 0);
+        assert_eq!(first_cost[&0], 1);
+        assert_eq!(first_cost[&3], 1);
+        assert_eq!(first_cost[&2], 2);
+        assert_eq!(first_cost[&4], 2);
+    }
+
+    #[test]
+    fn test_dijkstra_iter_early_stop() {
+        let mut v: Vec<Vec<(usize, i32)>> = Vec::with_capacity(5);
+        v.push(vec![(1, 1), (2, 1)]);
+        v.push(vec![(0, 1), (3, 1)]);
+        v.push(vec![(0, 1), (3, 2)]);
+        v.push(vec![(1, 1), (2, 2), (4, 1)]);
+        v.push(vec![(3, 1)]);
+
+        let start: usize = 0;
+        let destination: usize = 4;
+
+        // Stop as soon as the destination is first settled.
+        let reached = DijkstraIter::new(&v, &start)
+            .find(|(node, _, _)| **node == destination);
+        assert!(reached.is_some());
+        let (_, cost, _) = reached.unwrap();
+        assert_eq!(cost, 3);
+    }
+
     #[test]
     fn test_dijkstra_house() {
         let mut house: Vec<Vec<(usize, i32)>> = Vec::with_capacity(6);
@@ -9,6 +9,8 @@ use std::hash::Hash;
 use std::io::Write;
 use std::io::{BufRead, BufReader};
 mod dijkstra;
+mod routes;
+mod simulator;
 use dijkstra::dijkstra;
 
 type McAddr = Vec<(usize, String)>;
@@ -16,13 +18,22 @@ type McAddr = Vec<(usize, String)>;
 #[derive(Debug)]
 enum Error {
     /// Impossible to parse the file to crate a topo.
-    FileParse,
+    /// Carries the offending file name and the 1-based line number so failures
+    /// inside an included fragment can be located. The line number is 0 when the
+    /// failure concerns the file as a whole: it cannot be opened, or the
+    /// `*-routes.bin` artifact cannot be written.
+    FileParse(String, usize),
 
     /// Missing node.
     MissingNone,
 
     /// Too many multicast addresses.
     TooManyMcAddrs,
+
+    /// A destination is unreachable from some source because the topology is
+    /// partitioned (e.g. a `%unset` dropped the only link to a node). Carries
+    /// the source and the unreachable destination.
+    Unreachable(usize, usize),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -46,13 +57,34 @@ struct Args {
     /// Currently only supports a single multicast address.
     #[clap(short = 'm', long = "multicast", value_parser)]
     multicast: Option<String>,
+
+    /// Emit one forwarding entry per equal-cost next hop so the routers
+    /// load-balance across all shortest paths, instead of only the first one.
+    #[clap(long = "ecmp")]
+    ecmp: bool,
+
+    /// Also emit a compact binary routing-table artifact (`*-routes.bin`) that
+    /// downstream tooling can query one route at a time.
+    #[clap(long = "routes-bin")]
+    routes_bin: bool,
+
+    /// Replay packet forwarding over the generated tables to check for loops,
+    /// blackholes and suboptimal paths before loading them into Mininet.
+    #[clap(long = "validate")]
+    validate: bool,
+
+    /// Query the emitted `*-routes.bin` artifact (implies `--routes-bin`) for
+    /// the next hops of a single `"SRC DST"` pair and print them, the way
+    /// downstream tooling would read routes on demand.
+    #[clap(long = "query-route", value_parser)]
+    query_route: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Node {
     id: usize,
     name: String,
-    neighbours: Vec<(usize, i32)>, // (id, cost)
+    neighbours: Vec<(usize, u64)>, // (id, cost)
 }
 
 struct Graph {
@@ -62,50 +94,121 @@ struct Graph {
 
 impl Graph {
     fn from_file(file_path: &str) -> Result<Self> {
-        let file = std::fs::File::open(file_path).map_err(|_| Error::FileParse)?;
-
         let mut nodes = Vec::new(); // We do not know the size at first.
-        let reader = BufReader::new(file);
         let mut node2id = HashMap::new();
-        let mut current_id = 0;
-
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let split: Vec<&str> = line.split(' ').collect();
-            let a_id: usize = *node2id.entry(split[0].to_string()).or_insert(current_id);
-            if a_id == current_id {
-                current_id += 1;
-                let node = Node {
-                    name: split[0].to_string(),
-                    neighbours: Vec::new(),
-                    id: a_id,
-                };
-                nodes.push(node);
+        let mut visited = HashSet::new();
+
+        Self::parse_file(
+            std::path::Path::new(file_path),
+            &mut nodes,
+            &mut node2id,
+            &mut visited,
+        )?;
+
+        Ok(Graph { nodes, node2id })
+    }
+
+    /// Recursively parse an NTF fragment, following `%include` directives.
+    ///
+    /// Blank lines and comments (`#` or `;` prefixes) are skipped. A
+    /// `%include other.ntf` directive parses `other.ntf` relative to the
+    /// current file's directory, and `%unset A B` drops a previously declared
+    /// link between nodes `A` and `B`. Already-parsed files are tracked in
+    /// `visited` to break include cycles.
+    fn parse_file(
+        path: &std::path::Path,
+        nodes: &mut Vec<Node>,
+        node2id: &mut HashMap<String, usize>,
+        visited: &mut HashSet<std::path::PathBuf>,
+    ) -> Result<()> {
+        let display = path.display().to_string();
+        let canonical = path
+            .canonicalize()
+            .map_err(|_| Error::FileParse(display.clone(), 0))?;
+
+        // Break include cycles: a file is parsed at most once.
+        if !visited.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let file = std::fs::File::open(&canonical).map_err(|_| Error::FileParse(display.clone(), 0))?;
+        let reader = BufReader::new(file);
+
+        for (lineno, line) in reader.lines().enumerate() {
+            let line_nb = lineno + 1;
+            let line = line.map_err(|_| Error::FileParse(display.clone(), line_nb))?;
+            let line = line.trim();
+
+            // Skip blank lines and comments.
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
             }
 
-            let b_id: usize = *node2id.entry(split[1].to_string()).or_insert(current_id);
-            if b_id == current_id {
-                current_id += 1;
-                let node = Node {
-                    name: split[1].to_string(),
-                    neighbours: Vec::new(),
-                    id: b_id,
-                };
-                nodes.push(node);
+            // Directives.
+            if let Some(directive) = line.strip_prefix('%') {
+                let split: Vec<&str> = directive.split_whitespace().collect();
+                match split.first().copied() {
+                    Some("include") => {
+                        let target = split.get(1).ok_or_else(|| {
+                            Error::FileParse(display.clone(), line_nb)
+                        })?;
+                        let parent = canonical.parent().unwrap_or_else(|| std::path::Path::new(""));
+                        Self::parse_file(&parent.join(target), nodes, node2id, visited)?;
+                    }
+                    Some("unset") => {
+                        let (a, b) = match (split.get(1), split.get(2)) {
+                            (Some(a), Some(b)) => (*a, *b),
+                            _ => return Err(Error::FileParse(display.clone(), line_nb)),
+                        };
+                        if let (Some(&a_id), Some(&b_id)) = (node2id.get(a), node2id.get(b)) {
+                            nodes[a_id].neighbours.retain(|&(n, _)| n != b_id);
+                            nodes[b_id].neighbours.retain(|&(n, _)| n != a_id);
+                        }
+                    }
+                    _ => return Err(Error::FileParse(display.clone(), line_nb)),
+                }
+                continue;
+            }
+
+            // Regular edge line: `A B metric`.
+            let split: Vec<&str> = line.split_whitespace().collect();
+            if split.len() < 3 {
+                return Err(Error::FileParse(display.clone(), line_nb));
             }
 
-            // Get the metric from the line
-            let metric: i32 = split[2].parse::<i32>().unwrap();
+            let a_id = Self::node_id(split[0], nodes, node2id);
+            let b_id = Self::node_id(split[1], nodes, node2id);
+
+            // Get the metric from the line. Metrics are read as `u64` so wide
+            // IGP costs (e.g. bandwidth-derived) can accumulate along long
+            // paths without the overflow risk `i32` summation would carry.
+            let metric: u64 = split[2]
+                .parse::<u64>()
+                .map_err(|_| Error::FileParse(display.clone(), line_nb))?;
 
             // Add in neighbours adjacency list
             nodes[a_id].neighbours.push((b_id, metric));
             nodes[b_id].neighbours.push((a_id, metric));
         }
 
-        Ok(Graph { nodes, node2id })
+        Ok(())
     }
 
-    fn get_neighbours(&self) -> Vec<Vec<(usize, i32)>> {
+    /// Resolve a node name to its id, allocating a new node on first sight.
+    fn node_id(name: &str, nodes: &mut Vec<Node>, node2id: &mut HashMap<String, usize>) -> usize {
+        let next_id = nodes.len();
+        let id = *node2id.entry(name.to_string()).or_insert(next_id);
+        if id == next_id {
+            nodes.push(Node {
+                name: name.to_string(),
+                neighbours: Vec::new(),
+                id,
+            });
+        }
+        id
+    }
+
+    fn get_neighbours(&self) -> Vec<Vec<(usize, u64)>> {
         self.nodes
             .iter()
             .map(|node| node.neighbours.to_owned())
@@ -117,6 +220,9 @@ impl Graph {
         directory: &str,
         file_prefix: &str,
         ipv4: bool,
+        ecmp: bool,
+        routes_bin: bool,
+        validate: bool,
         mc_addrs: Option<McAddr>,
     ) -> Result<()> {
         let nb_nodes = self.nodes.len();
@@ -206,15 +312,31 @@ impl Graph {
         // Finally all the paths must be statically added for each router.
         let prefix_length = if ipv4 { 32 } else { 64 };
         let mut s = String::new();
+        // Per-source next-hop matrix, kept only when the binary artifact or the
+        // forwarding validation is requested.
+        let mut routes_matrix: Vec<Vec<Vec<usize>>> = Vec::with_capacity(nb_nodes);
         for source in 0..nb_nodes {
             let predecessors = dijkstra(&successors, &source).unwrap();
             debug!("PREDECESSORS: {:?}", predecessors);
 
+            // A `%unset` may have partitioned the graph: a node with no
+            // predecessor entry is unreachable from `source` and would
+            // otherwise panic in the interface-recovery walk below. Surface it
+            // as a clean error instead.
+            for dst in 0..nb_nodes {
+                if !predecessors.contains_key(&dst) {
+                    return Err(Error::Unreachable(source, dst));
+                }
+            }
+
             // Construct the next hop mapping, possibly there are multiple paths so multiple output interfaces.
             let next_hop: Vec<Vec<usize>> = (0..nb_nodes)
                 .map(|i| get_all_out_interfaces_to_destination(&predecessors, source, i))
                 .collect();
             debug!("MAPPING: {:?}", next_hop);
+            if routes_bin || validate {
+                routes_matrix.push(next_hop.clone());
+            }
             let node = topo.get(source).unwrap();
 
             // For each destination, find the correct next hop.
@@ -222,35 +344,38 @@ impl Graph {
                 if i == source {
                     continue; // Same node.
                 }
-                // Only use the first path.
-                // `hop` is the node id of the next hop
-                let hop = dst[0];
-
-                let link_ip = links.get(&(source, hop)).unwrap();
                 let destination_ip = loopbacks.get(&i).unwrap();
 
-                // Get the output interface of the node.
-                let output_itf = node.neighbours.iter().position(|&(r, _)| r == hop).unwrap();
+                // In ECMP mode install one forwarding entry per equal-cost next
+                // hop; otherwise only keep the first path.
+                let hops: &[usize] = if ecmp { &dst } else { &dst[0..1] };
+                for &hop in hops {
+                    // `hop` is the node id of the next hop.
+                    let link_ip = links.get(&(source, hop)).unwrap();
 
-                // Hop is not correct here!
-                writeln!(
-                    s,
-                    "{} {} {} {}",
-                    source, output_itf, link_ip, destination_ip
-                )
-                .unwrap();
+                    // Get the output interface of the node.
+                    let output_itf =
+                        node.neighbours.iter().position(|&(r, _)| r == hop).unwrap();
 
-                // Add the same path for each link local of the destination node.
-                for (&(_, dst), link) in links.iter().filter(|(&(src, _), _)| src == i) {
-                    if dst == source {
-                        continue;
-                    }
                     writeln!(
                         s,
-                        "{} {} {} {}/{}",
-                        source, output_itf, link_ip, link, prefix_length
+                        "{} {} {} {}",
+                        source, output_itf, link_ip, destination_ip
                     )
                     .unwrap();
+
+                    // Add the same path for each link local of the destination node.
+                    for (&(_, dst), link) in links.iter().filter(|(&(src, _), _)| src == i) {
+                        if dst == source {
+                            continue;
+                        }
+                        writeln!(
+                            s,
+                            "{} {} {} {}/{}",
+                            source, output_itf, link_ip, link, prefix_length
+                        )
+                        .unwrap();
+                    }
                 }
             }
         }
@@ -260,6 +385,35 @@ impl Graph {
         let mut file = File::create(&path).unwrap();
         file.write_all(s.as_bytes()).unwrap();
 
+        // Optionally replay forwarding over the generated tables.
+        if validate {
+            let report = simulator::validate(&successors, &routes_matrix);
+            for anomaly in &report.anomalies {
+                warn!("forwarding anomaly: {:?}", anomaly);
+            }
+            for (source, destination, actual, best) in &report.suboptimal {
+                warn!(
+                    "suboptimal path {} -> {}: cost {} (optimal {})",
+                    source, destination, actual, best
+                );
+            }
+            println!("End-to-end path cost histogram (cost: count):");
+            for (cost, count) in &report.histogram {
+                println!("{}: {}", cost, count);
+            }
+            if report.is_clean() {
+                println!("validation: all forwarding paths are loop-free and optimal");
+            }
+        }
+
+        // Optionally dump the next-hop matrix as a compact binary artifact.
+        if routes_bin {
+            let pathname = format!("{}-routes.bin", file_prefix);
+            let path = std::path::Path::new(directory).join(pathname);
+            routes::write_routes(&path, nb_nodes, &loopbacks, &routes_matrix)
+                .map_err(|_| Error::FileParse(path.display().to_string(), 0))?;
+        }
+
         // Multicast routes to be installed.
         if let Some(mc_addrs) = mc_addrs.as_ref() {
             let mut s = String::new();
@@ -328,11 +482,11 @@ fn get_all_out_interfaces_to_destination(
 fn get_mc_addrs(filename: &str, graph: &Graph) -> Result<McAddr> {
     let mut out = McAddr::new();
 
-    let file = std::fs::File::open(filename).map_err(|_| Error::FileParse)?;
+    let file = std::fs::File::open(filename).map_err(|_| Error::FileParse(filename.to_string(), 0))?;
     let reader = BufReader::new(file);
 
-    for line in reader.lines() {
-        let line = line.unwrap();
+    for (lineno, line) in reader.lines().enumerate() {
+        let line = line.map_err(|_| Error::FileParse(filename.to_string(), lineno + 1))?;
         let split: Vec<_> = line.split(' ').collect();
         let id: usize = *graph.node2id.get(split[0]).ok_or(Error::MissingNone)?;
         out.push((id, split[1].to_string()));
@@ -365,7 +519,33 @@ fn main() {
     };
     let path = std::path::Path::new(&args.topo_file);
     let filename = path.file_stem().unwrap().to_str().unwrap();
+    let routes_bin = args.routes_bin || args.query_route.is_some();
     graph
-        .get_mininet_config(&args.directory, filename, args.ipv4, mc_addrs)
+        .get_mininet_config(
+            &args.directory,
+            filename,
+            args.ipv4,
+            args.ecmp,
+            routes_bin,
+            args.validate,
+            mc_addrs,
+        )
         .unwrap();
+
+    // Read a single route back out of the compact artifact on demand.
+    if let Some(query) = args.query_route.as_ref() {
+        let split: Vec<&str> = query.split_whitespace().collect();
+        let src: usize = split[0].parse().unwrap();
+        let dst: usize = split[1].parse().unwrap();
+        let pathname = format!("{}-routes.bin", filename);
+        let path = std::path::Path::new(&args.directory).join(pathname);
+        let table = routes::RoutingTable::open(&path).unwrap();
+        println!(
+            "next hops {} -> {} ({}): {:?}",
+            src,
+            table.loopback(dst),
+            table.node_count(),
+            table.next_hop(src, dst),
+        );
+    }
 }
@@ -0,0 +1,208 @@
+//! Compact binary routing-table artifact (`*-routes.bin`).
+//!
+//! For large topologies the line-based text outputs (`*-paths.txt`,
+//! `*-links.txt`, `*-loopbacks.txt`) can grow to hundreds of megabytes. This
+//! module defines a fixed-layout binary dump of the per-source next-hop matrix
+//! that downstream tooling can open and query one route at a time, without
+//! deserializing the whole thing.
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! magic        : 4 bytes = b"MNRT"
+//! version      : u32
+//! node_count   : u32 (= n)
+//! loopbacks    : n * LOOPBACK_WIDTH bytes, UTF-8 zero-padded
+//! index        : n * n entries of (offset: u32, len: u32) into the data pool
+//! data         : flat pool of u32 next-hop node ids
+//! ```
+//!
+//! The `(offset, len)` index lets [`RoutingTable::next_hop`] slice a single row
+//! out of the data pool on demand.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Magic bytes at the start of a routes artifact.
+pub const MAGIC: &[u8; 4] = b"MNRT";
+
+/// Current artifact version.
+pub const VERSION: u32 = 1;
+
+/// Fixed width, in bytes, of a loopback entry (UTF-8, zero-padded).
+pub const LOOPBACK_WIDTH: usize = 48;
+
+const HEADER_LEN: usize = 4 + 4 + 4;
+
+/// Serialize the routing information to `path`.
+///
+/// `matrix[src][dst]` holds the equal-cost next-hop node ids from `src` towards
+/// `dst`. Each entry is stored as given (`get_mininet_config` passes `[src]`
+/// for `src == dst`); an entry missing from `matrix` is stored as empty.
+pub fn write_routes(
+    path: &Path,
+    node_count: usize,
+    loopbacks: &HashMap<usize, String>,
+    matrix: &[Vec<Vec<usize>>],
+) -> io::Result<()> {
+    let mut out = Vec::new();
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&(node_count as u32).to_le_bytes());
+
+    // Loopback table, one fixed-width slot per node.
+    for i in 0..node_count {
+        let lo = loopbacks.get(&i).map(String::as_str).unwrap_or("");
+        let mut slot = [0u8; LOOPBACK_WIDTH];
+        let bytes = lo.as_bytes();
+        let len = bytes.len().min(LOOPBACK_WIDTH);
+        slot[..len].copy_from_slice(&bytes[..len]);
+        out.extend_from_slice(&slot);
+    }
+
+    // Build the data pool and the (offset, len) index in a single pass.
+    let mut index = Vec::with_capacity(node_count * node_count * 2);
+    let mut data: Vec<u32> = Vec::new();
+    for src in 0..node_count {
+        for dst in 0..node_count {
+            let hops = matrix.get(src).and_then(|row| row.get(dst));
+            let offset = data.len() as u32;
+            let len = match hops {
+                Some(hops) => {
+                    for &hop in hops {
+                        data.push(hop as u32);
+                    }
+                    hops.len() as u32
+                }
+                None => 0,
+            };
+            index.push(offset);
+            index.push(len);
+        }
+    }
+
+    for v in index {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    for v in data {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+
+    let mut file = File::create(path)?;
+    file.write_all(&out)?;
+    Ok(())
+}
+
+/// A lazily-read view over a `*-routes.bin` artifact.
+///
+/// The backing bytes are kept verbatim (as if memory-mapped) and accessors
+/// decode only the fields they touch: [`next_hop`](Self::next_hop) reads a
+/// single `(offset, len)` index entry and pulls out just that row, so the whole
+/// data pool is never materialised up front.
+pub struct RoutingTable {
+    bytes: Vec<u8>,
+    node_count: usize,
+    loopbacks_off: usize,
+    index_off: usize,
+    data_off: usize,
+}
+
+impl RoutingTable {
+    /// Open and validate a routes artifact.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad magic"));
+        }
+        let version = read_u32(&bytes, 4);
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported version",
+            ));
+        }
+        let node_count = read_u32(&bytes, 8) as usize;
+
+        let loopbacks_off = HEADER_LEN;
+        let index_off = loopbacks_off + node_count * LOOPBACK_WIDTH;
+        let data_off = index_off + node_count * node_count * 2 * 4;
+
+        // Only the header and offsets are looked at here; the data pool stays as
+        // raw bytes and is decoded one row at a time by `next_hop`.
+        Ok(RoutingTable {
+            bytes,
+            node_count,
+            loopbacks_off,
+            index_off,
+            data_off,
+        })
+    }
+
+    /// Number of nodes in the topology.
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// The loopback string of `node`.
+    pub fn loopback(&self, node: usize) -> &str {
+        let start = self.loopbacks_off + node * LOOPBACK_WIDTH;
+        let slot = &self.bytes[start..start + LOOPBACK_WIDTH];
+        let end = slot.iter().position(|&b| b == 0).unwrap_or(LOOPBACK_WIDTH);
+        std::str::from_utf8(&slot[..end]).unwrap_or("")
+    }
+
+    /// The equal-cost next-hop node ids from `src` towards `dst`.
+    ///
+    /// Only the requested row is decoded: the `(offset, len)` index entry is
+    /// read on demand and just that row's `u32`s are pulled out of the mapped
+    /// bytes, so opening a hundred-megabyte artifact never decodes the whole
+    /// pool.
+    pub fn next_hop(&self, src: usize, dst: usize) -> Vec<u32> {
+        let entry = self.index_off + (src * self.node_count + dst) * 2 * 4;
+        let offset = read_u32(&self.bytes, entry) as usize;
+        let len = read_u32(&self.bytes, entry + 4) as usize;
+        let start = self.data_off + offset * 4;
+        (0..len).map(|k| read_u32(&self.bytes, start + k * 4)).collect()
+    }
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mininet-static-builder-routes-test.bin");
+
+        let mut loopbacks = HashMap::new();
+        loopbacks.insert(0, "11.0.0.1/32".to_string());
+        loopbacks.insert(1, "11.0.1.1/32".to_string());
+        loopbacks.insert(2, "11.0.2.1/32".to_string());
+
+        // Node 0 reaches 2 via the ECMP pair {1, 2}.
+        let matrix: Vec<Vec<Vec<usize>>> = vec![
+            vec![vec![], vec![1], vec![1, 2]],
+            vec![vec![0], vec![], vec![2]],
+            vec![vec![0], vec![1], vec![]],
+        ];
+
+        write_routes(&path, 3, &loopbacks, &matrix).unwrap();
+
+        let table = RoutingTable::open(&path).unwrap();
+        assert_eq!(table.node_count(), 3);
+        assert_eq!(table.loopback(1), "11.0.1.1/32");
+        assert_eq!(table.next_hop(0, 1), vec![1]);
+        assert_eq!(table.next_hop(0, 2), vec![1, 2]);
+        assert!(table.next_hop(0, 0).is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,240 @@
+//! Discrete-event forwarding validator.
+//!
+//! Before the generated static routes are ever loaded into Mininet, this
+//! subsystem replays packet forwarding over the per-source next-hop tables that
+//! [`get_mininet_config`](crate::Graph::get_mininet_config) computes. For every
+//! `(source, destination)` pair it follows the installed next hops hop-by-hop,
+//! flagging forwarding loops, blackholes and next-hop/metric inconsistencies,
+//! and comparing the realised path cost against the Dijkstra-optimal cost.
+//!
+//! Forwarding is driven by a tiny event queue keyed by simulated arrival time,
+//! so per-link latencies (the edge weights) accumulate along each path and the
+//! end-to-end costs can be summarised as a histogram.
+//!
+//! Scope: the validator replays the next-hop *node ids* of each source's table.
+//! It therefore catches loops, blackholes, next-hop/metric inconsistencies and
+//! suboptimal costs, but it does not inspect the `output_itf` interface-index
+//! arithmetic written to `*-paths.txt` — that representation is never fed to the
+//! simulator, so an off-by-one there is out of this subsystem's reach. Where a
+//! table holds several equal-cost next hops, only the first one is followed.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+
+use crate::dijkstra::{DijkstraIter, Weight};
+
+/// A forwarding anomaly discovered while replaying the routing tables.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Anomaly {
+    /// A node was revisited while forwarding towards `destination`.
+    Loop {
+        source: usize,
+        destination: usize,
+        at: usize,
+    },
+    /// A node without any next hop towards a reachable `destination`.
+    Blackhole {
+        source: usize,
+        destination: usize,
+        at: usize,
+    },
+    /// The installed next hop is not an adjacent neighbour (missing link/metric).
+    Inconsistency {
+        source: usize,
+        destination: usize,
+        at: usize,
+        next_hop: usize,
+    },
+}
+
+/// The outcome of a validation run.
+pub struct Report<W> {
+    /// All anomalies, in the order they were detected.
+    pub anomalies: Vec<Anomaly>,
+    /// Histogram of end-to-end path costs for the pairs that were delivered.
+    pub histogram: BTreeMap<W, usize>,
+    /// Pairs whose realised cost exceeds the Dijkstra-optimal cost.
+    pub suboptimal: Vec<(usize, usize, W, W)>,
+}
+
+impl<W> Report<W> {
+    /// Whether the routing tables validated without any anomaly and without
+    /// any suboptimal path.
+    pub fn is_clean(&self) -> bool {
+        self.anomalies.is_empty() && self.suboptimal.is_empty()
+    }
+}
+
+/// Replay forwarding over `matrix` (the per-source next-hop node ids) using the
+/// link weights in `adjacency`, and report any anomaly.
+// `&Vec` rather than `&[_]`: the `Graph` trait is only implemented for the
+// owned `Vec<Vec<(usize, W)>>`, which the optimal-cost Dijkstra needs.
+#[allow(clippy::ptr_arg)]
+pub fn validate<W: Weight>(
+    adjacency: &Vec<Vec<(usize, W)>>,
+    matrix: &[Vec<Vec<usize>>],
+) -> Report<W> {
+    let nb_nodes = adjacency.len();
+
+    // Dijkstra-optimal cost from every source to every node.
+    let mut optimal: Vec<HashMap<usize, W>> = Vec::with_capacity(nb_nodes);
+    for source in 0..nb_nodes {
+        let mut costs = HashMap::new();
+        for (node, cost, _from) in DijkstraIter::new(adjacency, &source) {
+            costs.entry(*node).or_insert(cost);
+        }
+        optimal.push(costs);
+    }
+
+    let mut anomalies = Vec::new();
+    let mut histogram: BTreeMap<W, usize> = BTreeMap::new();
+    let mut suboptimal = Vec::new();
+
+    // Event queue keyed by simulated arrival time. The insertion counter breaks
+    // ties deterministically. Each event is `(time, seq, node, source, dst)`.
+    let mut queue: BinaryHeap<Reverse<(W, usize, usize, usize, usize)>> = BinaryHeap::new();
+    let mut seq = 0;
+    for source in 0..nb_nodes {
+        for destination in 0..nb_nodes {
+            if source == destination {
+                continue;
+            }
+            queue.push(Reverse((W::zero(), seq, source, source, destination)));
+            seq += 1;
+        }
+    }
+
+    // Nodes already traversed by a given (source, destination) packet.
+    let mut visited: HashMap<(usize, usize), HashSet<usize>> = HashMap::new();
+
+    while let Some(Reverse((time, _, node, source, destination))) = queue.pop() {
+        if node == destination {
+            *histogram.entry(time).or_insert(0) += 1;
+            if let Some(&best) = optimal[source].get(&destination) {
+                if time > best {
+                    suboptimal.push((source, destination, time, best));
+                }
+            }
+            continue;
+        }
+
+        // Loop detection.
+        if !visited
+            .entry((source, destination))
+            .or_default()
+            .insert(node)
+        {
+            anomalies.push(Anomaly::Loop {
+                source,
+                destination,
+                at: node,
+            });
+            continue;
+        }
+
+        // Blackhole: no next hop towards a reachable destination.
+        let hops = matrix.get(node).and_then(|row| row.get(destination));
+        let hop = match hops.and_then(|hops| hops.first()) {
+            Some(&hop) => hop,
+            None => {
+                if optimal[source].contains_key(&destination) {
+                    anomalies.push(Anomaly::Blackhole {
+                        source,
+                        destination,
+                        at: node,
+                    });
+                }
+                continue;
+            }
+        };
+
+        // Next-hop/metric inconsistency: the chosen hop must be adjacent.
+        let latency = adjacency[node].iter().find(|&&(n, _)| n == hop).map(|&(_, w)| w);
+        let latency = match latency {
+            Some(latency) => latency,
+            None => {
+                anomalies.push(Anomaly::Inconsistency {
+                    source,
+                    destination,
+                    at: node,
+                    next_hop: hop,
+                });
+                continue;
+            }
+        };
+
+        queue.push(Reverse((time + latency, seq, hop, source, destination)));
+        seq += 1;
+    }
+
+    Report {
+        anomalies,
+        histogram,
+        suboptimal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small line topology 0 - 1 - 2 with unit links.
+    fn line() -> Vec<Vec<(usize, i32)>> {
+        vec![vec![(1, 1)], vec![(0, 1), (2, 1)], vec![(1, 1)]]
+    }
+
+    #[test]
+    fn test_validate_clean() {
+        let adj = line();
+        // Correct next hops along the line.
+        let matrix: Vec<Vec<Vec<usize>>> = vec![
+            vec![vec![], vec![1], vec![1]],
+            vec![vec![0], vec![], vec![2]],
+            vec![vec![1], vec![1], vec![]],
+        ];
+        let report = validate(&adj, &matrix);
+        assert!(report.is_clean());
+        // 0->2 and 2->0 cost 2, the four one-hop pairs cost 1.
+        assert_eq!(report.histogram.get(&1), Some(&4));
+        assert_eq!(report.histogram.get(&2), Some(&2));
+    }
+
+    #[test]
+    fn test_validate_detects_loop() {
+        let adj = line();
+        // Node 1 points back to 0 for destination 2: 0 -> 1 -> 0 -> ...
+        let matrix: Vec<Vec<Vec<usize>>> = vec![
+            vec![vec![], vec![1], vec![1]],
+            vec![vec![0], vec![], vec![0]],
+            vec![vec![1], vec![1], vec![]],
+        ];
+        let report = validate(&adj, &matrix);
+        assert!(report.anomalies.iter().any(|a| matches!(
+            a,
+            Anomaly::Loop {
+                destination: 2,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_validate_detects_blackhole() {
+        let adj = line();
+        // Node 1 has no next hop towards 2.
+        let matrix: Vec<Vec<Vec<usize>>> = vec![
+            vec![vec![], vec![1], vec![1]],
+            vec![vec![0], vec![], vec![]],
+            vec![vec![1], vec![1], vec![]],
+        ];
+        let report = validate(&adj, &matrix);
+        assert!(report.anomalies.iter().any(|a| matches!(
+            a,
+            Anomaly::Blackhole {
+                at: 1,
+                destination: 2,
+                ..
+            }
+        )));
+    }
+}